@@ -0,0 +1,213 @@
+use futures::future::join_all;
+use std::collections::HashMap;
+
+/// Absolute ceiling on comment recursion depth, enforced regardless of what
+/// a caller requests, so a fetch is always guaranteed to terminate.
+pub const MAX_DEPTH: u32 = 50;
+
+/// Firebase fetches issued concurrently per BFS level.
+const CONCURRENCY_CHUNK: usize = 50;
+
+/**
+ * Fetches a comment tree breadth-first starting from `root_ids`, instead of
+ * recursing per comment, so fan-out at a wide or deep level doesn't build
+ * unbounded async future nesting. Each level's items are fetched through
+ * `join_all` in chunks of `CONCURRENCY_CHUNK` to keep concurrent requests
+ * against the Firebase endpoint bounded.
+ *
+ * Returns every fetched item keyed by id; use `assemble` to stitch the map
+ * into a `CommentWithReplies` tree once fetching completes.
+ */
+pub async fn fetch_tree(root_ids: &[u32], max_depth: u32) -> HashMap<u32, crate::HNItem> {
+    let mut items = HashMap::new();
+    let mut frontier = initial_frontier(root_ids, max_depth);
+
+    while !frontier.is_empty() {
+        let ids: Vec<u32> = frontier.iter().map(|&(id, _)| id).collect();
+        let fetched = fetch_level(&ids).await;
+
+        frontier = advance_frontier(&frontier, &fetched);
+        items.extend(fetched);
+    }
+
+    items
+}
+
+/// Seeds the BFS work queue with each root at `max_depth`, clamped to
+/// `MAX_DEPTH` regardless of what the caller asked for.
+fn initial_frontier(root_ids: &[u32], max_depth: u32) -> Vec<(u32, u32)> {
+    let max_depth = max_depth.min(MAX_DEPTH);
+    root_ids.iter().map(|&id| (id, max_depth)).collect()
+}
+
+/// Computes the next BFS level from the current frontier and the items that
+/// were just fetched for it. An id at `remaining_depth == 0` doesn't expand
+/// (this is what makes `depth=0` return top-level-only comments), and an id
+/// missing from `fetched` (a failed fetch) is simply skipped rather than
+/// poisoning the rest of the traversal.
+fn advance_frontier(
+    frontier: &[(u32, u32)],
+    fetched: &HashMap<u32, crate::HNItem>,
+) -> Vec<(u32, u32)> {
+    let mut next_frontier = Vec::new();
+
+    for &(id, remaining_depth) in frontier {
+        if remaining_depth == 0 {
+            continue;
+        }
+        if let Some(item) = fetched.get(&id) {
+            if let Some(kids) = &item.kids {
+                for &kid in kids {
+                    next_frontier.push((kid, remaining_depth - 1));
+                }
+            }
+        }
+    }
+
+    next_frontier
+}
+
+async fn fetch_level(ids: &[u32]) -> HashMap<u32, crate::HNItem> {
+    let mut out = HashMap::new();
+
+    for chunk in ids.chunks(CONCURRENCY_CHUNK) {
+        let fetches = chunk.iter().map(|&id| crate::fetch_hn_item(&id.to_string()));
+        let results = join_all(fetches).await;
+
+        for (&id, result) in chunk.iter().zip(results) {
+            if let Ok(item) = result {
+                out.insert(id, item);
+            }
+        }
+    }
+
+    out
+}
+
+/**
+ * Stitches a fetched item map into a `CommentWithReplies` tree rooted at
+ * `id`. A node whose `text` is missing (deleted/dead comments) is dropped
+ * along with everything beneath it, matching the original recursive
+ * fetcher's behavior of discarding such replies.
+ */
+pub fn assemble(id: u32, items: &HashMap<u32, crate::HNItem>) -> Option<crate::CommentWithReplies> {
+    let item = items.get(&id)?;
+
+    let replies = item
+        .kids
+        .as_ref()
+        .map(|kids| kids.iter().filter_map(|&kid| assemble(kid, items)).collect())
+        .unwrap_or_default();
+
+    let comment = crate::CommentWithReplies {
+        id,
+        by: item.by.clone(),
+        time: item.time,
+        text: item.text.clone(),
+        replies,
+    };
+
+    comment.text.is_some().then_some(comment)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn comment_item(id: u32, text: Option<&str>, kids: Option<Vec<u32>>) -> crate::HNItem {
+        crate::HNItem {
+            id,
+            item_type: "comment".to_string(),
+            deleted: None,
+            by: Some("someone".to_string()),
+            time: Some(1000),
+            text: text.map(str::to_string),
+            dead: None,
+            parent: None,
+            poll: None,
+            kids,
+            url: None,
+            score: None,
+            title: None,
+            parts: None,
+            descendants: None,
+        }
+    }
+
+    #[test]
+    fn initial_frontier_clamps_to_max_depth() {
+        let frontier = initial_frontier(&[1, 2], 1_000);
+        assert_eq!(frontier, vec![(1, MAX_DEPTH), (2, MAX_DEPTH)]);
+    }
+
+    #[test]
+    fn initial_frontier_respects_a_smaller_requested_depth() {
+        let frontier = initial_frontier(&[1], 3);
+        assert_eq!(frontier, vec![(1, 3)]);
+    }
+
+    #[test]
+    fn depth_zero_does_not_expand_to_kids() {
+        let mut fetched = HashMap::new();
+        fetched.insert(1, comment_item(1, Some("hi"), Some(vec![2, 3])));
+
+        let next = advance_frontier(&[(1, 0)], &fetched);
+
+        assert!(next.is_empty());
+    }
+
+    #[test]
+    fn a_failed_fetch_is_skipped_without_poisoning_the_rest() {
+        // id 2 is in the frontier but missing from `fetched`, simulating a
+        // failed Firebase fetch for that one item.
+        let mut fetched = HashMap::new();
+        fetched.insert(1, comment_item(1, Some("hi"), Some(vec![10, 11])));
+
+        let next = advance_frontier(&[(1, 5), (2, 5)], &fetched);
+
+        assert_eq!(next, vec![(10, 4), (11, 4)]);
+    }
+
+    #[test]
+    fn assemble_stitches_nested_replies() {
+        let mut items = HashMap::new();
+        items.insert(1, comment_item(1, Some("parent"), Some(vec![2])));
+        items.insert(2, comment_item(2, Some("child"), None));
+
+        let comment = assemble(1, &items).expect("root has text");
+
+        assert_eq!(comment.id, 1);
+        assert_eq!(comment.replies.len(), 1);
+        assert_eq!(comment.replies[0].id, 2);
+        assert!(comment.replies[0].replies.is_empty());
+    }
+
+    #[test]
+    fn assemble_drops_a_node_with_no_text_and_its_subtree() {
+        let mut items = HashMap::new();
+        items.insert(1, comment_item(1, None, Some(vec![2])));
+        items.insert(2, comment_item(2, Some("child"), None));
+
+        assert!(assemble(1, &items).is_none());
+    }
+
+    #[test]
+    fn assemble_skips_a_missing_reply_without_dropping_siblings() {
+        let mut items = HashMap::new();
+        // id 3 is a kid of the root but never made it into the map
+        // (a failed fetch), while id 2 fetched fine.
+        items.insert(1, comment_item(1, Some("parent"), Some(vec![2, 3])));
+        items.insert(2, comment_item(2, Some("child"), None));
+
+        let comment = assemble(1, &items).expect("root has text");
+
+        assert_eq!(comment.replies.len(), 1);
+        assert_eq!(comment.replies[0].id, 2);
+    }
+
+    #[test]
+    fn assemble_returns_none_for_a_missing_root() {
+        let items = HashMap::new();
+        assert!(assemble(1, &items).is_none());
+    }
+}
@@ -0,0 +1,132 @@
+use worker::{Error, Result};
+
+/**
+ * Opaque pagination cursor encoding the story a page of top-level comments
+ * was fetched from, plus the offset into that story's `kids` array the
+ * next page should resume at.
+ *
+ * The cursor is serialized as `base64("<story_id>:<start_index>")` so it
+ * stays a single opaque token on the wire rather than leaking structure,
+ * while remaining cheap to encode/decode without pulling in a JSON step.
+ */
+pub struct Cursor {
+    pub story_id: u32,
+    pub start_index: usize,
+}
+
+const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+impl Cursor {
+    pub fn encode(story_id: u32, start_index: usize) -> String {
+        encode_base64(format!("{}:{}", story_id, start_index).as_bytes())
+    }
+
+    pub fn decode(cursor: &str) -> Result<Cursor> {
+        let bytes = decode_base64(cursor)
+            .ok_or_else(|| Error::from("Invalid cursor encoding"))?;
+        let raw = String::from_utf8(bytes).map_err(|_| Error::from("Invalid cursor encoding"))?;
+
+        let (story_id, start_index) = raw
+            .split_once(':')
+            .ok_or_else(|| Error::from("Invalid cursor contents"))?;
+
+        Ok(Cursor {
+            story_id: story_id
+                .parse()
+                .map_err(|_| Error::from("Invalid cursor contents"))?,
+            start_index: start_index
+                .parse()
+                .map_err(|_| Error::from("Invalid cursor contents"))?,
+        })
+    }
+}
+
+pub(crate) fn encode_base64(input: &[u8]) -> String {
+    let mut out = String::with_capacity((input.len() + 2) / 3 * 4);
+
+    for chunk in input.chunks(3) {
+        let b0 = chunk[0];
+        let b1 = chunk.get(1).copied();
+        let b2 = chunk.get(2).copied();
+
+        out.push(ALPHABET[(b0 >> 2) as usize] as char);
+        out.push(ALPHABET[(((b0 & 0x03) << 4) | (b1.unwrap_or(0) >> 4)) as usize] as char);
+
+        if let Some(b1) = b1 {
+            out.push(ALPHABET[(((b1 & 0x0f) << 2) | (b2.unwrap_or(0) >> 6)) as usize] as char);
+        } else {
+            out.push('=');
+        }
+
+        if let Some(b2) = b2 {
+            out.push(ALPHABET[(b2 & 0x3f) as usize] as char);
+        } else {
+            out.push('=');
+        }
+    }
+
+    out
+}
+
+pub(crate) fn decode_base64(input: &str) -> Option<Vec<u8>> {
+    let input = input.trim_end_matches('=');
+    let mut out = Vec::with_capacity(input.len() / 4 * 3);
+    let mut buf = 0u32;
+    let mut bits = 0u32;
+
+    for c in input.bytes() {
+        let val = ALPHABET.iter().position(|&a| a == c)? as u32;
+        buf = (buf << 6) | val;
+        bits += 6;
+
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buf >> bits) as u8);
+        }
+    }
+
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base64_round_trips_various_lengths() {
+        // 0, 1, 2, and 3 trailing bytes exercise every padding case.
+        for input in [
+            &b""[..],
+            &b"a"[..],
+            &b"ab"[..],
+            &b"abc"[..],
+            &b"abcd"[..],
+            &b"hello, world!"[..],
+        ] {
+            let encoded = encode_base64(input);
+            let decoded = decode_base64(&encoded).expect("valid base64 should decode");
+            assert_eq!(decoded, input);
+        }
+    }
+
+    #[test]
+    fn decode_base64_rejects_invalid_characters() {
+        assert!(decode_base64("not valid base64!!").is_none());
+    }
+
+    #[test]
+    fn cursor_round_trips() {
+        for (story_id, start_index) in [(1u32, 0usize), (36919310, 37), (u32::MAX, 12345)] {
+            let token = Cursor::encode(story_id, start_index);
+            let decoded = Cursor::decode(&token).expect("encoded cursor should decode");
+            assert_eq!(decoded.story_id, story_id);
+            assert_eq!(decoded.start_index, start_index);
+        }
+    }
+
+    #[test]
+    fn decode_rejects_malformed_cursor() {
+        assert!(Cursor::decode("not-a-real-cursor!!").is_err());
+        assert!(Cursor::decode(&encode_base64(b"missing-separator")).is_err());
+    }
+}
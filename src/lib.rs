@@ -1,12 +1,21 @@
-use futures::future::join_all;
 use serde::{Deserialize, Serialize};
 use worker::*;
 
+mod comments;
+mod cursor;
+mod lists;
+mod render;
+mod sort;
+
+use cursor::Cursor;
+use render::RenderFormat;
+use sort::SortMode;
+
 /**
  * HackerNews item structure, can be used for both stories and comments
  * API endpoint: https://hacker-news.firebaseio.com/v0/item/${id}.json
  */
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct HNItem {
     id: u32,
     #[serde(rename = "type")]
@@ -47,6 +56,68 @@ struct CommentWithReplies {
 struct StoryWithComments {
     story: HNItem,
     comments: Vec<CommentWithReplies>,
+    next_cursor: Option<String>,
+}
+
+/**
+ * A comment thread rooted at an arbitrary comment, with its ancestor chain
+ * (immediate parent first) up to the story it belongs to. `story` is `None`
+ * if the upward walk couldn't be completed (an ancestor failed to fetch
+ * before a story was reached).
+ */
+#[derive(Serialize)]
+struct CommentThread {
+    ancestors: Vec<HNItem>,
+    comment: CommentWithReplies,
+    story: Option<HNItem>,
+}
+
+/// Query parameters shared by the `/api/story/{id}` and `/api/comment/{id}` routes.
+struct CommonParams {
+    max_depth: Option<u32>,
+    limit: Option<usize>,
+    after: Option<String>,
+    sort_mode: SortMode,
+    format: RenderFormat,
+}
+
+fn parse_common_params(url: &Url) -> Result<CommonParams> {
+    let mut max_depth = None;
+    let mut limit = None;
+    let mut after = None;
+    let mut sort_mode = SortMode::Best;
+    let mut format = RenderFormat::Html;
+
+    for (key, value) in url.query_pairs() {
+        match key.as_ref() {
+            "depth" => {
+                if let Ok(depth) = value.parse::<u32>() {
+                    max_depth = Some(depth)
+                }
+            }
+            "limit" => {
+                if let Ok(lim) = value.parse::<usize>() {
+                    limit = Some(lim)
+                }
+            }
+            "after" => after = Some(value.into_owned()),
+            "sort" => {
+                sort_mode = SortMode::parse(&value).ok_or_else(|| Error::from("Invalid sort mode"))?
+            }
+            "format" => {
+                format = RenderFormat::parse(&value).ok_or_else(|| Error::from("Invalid format"))?
+            }
+            _ => {}
+        }
+    }
+
+    Ok(CommonParams {
+        max_depth,
+        limit,
+        after,
+        sort_mode,
+        format,
+    })
 }
 
 #[event(fetch)]
@@ -69,25 +140,100 @@ async fn main(req: Request, _env: Env, _ctx: Context) -> Result<Response> {
             return Response::error("Invalid Story ID", 400);
         }
 
-        let query_params = url.query_pairs();
-        let mut max_depth = None;
+        let params = match parse_common_params(&url) {
+            Ok(params) => params,
+            Err(e) => return Response::error(e.to_string(), 400),
+        };
+
+        let start_index = match &params.after {
+            Some(cursor) => match Cursor::decode(cursor) {
+                Ok(parsed) if parsed.story_id.to_string() == story_id => parsed.start_index,
+                Ok(_) => return Response::error("Cursor does not match story ID", 400),
+                Err(e) => return Response::error(format!("Invalid cursor: {}", e), 400),
+            },
+            None => 0,
+        };
+
+        match fetch_story_with_comments(
+            story_id,
+            params.max_depth,
+            params.limit,
+            start_index,
+            params.sort_mode,
+        )
+        .await
+        {
+            Ok(mut story_with_comments) => {
+                render::render_tree(&mut story_with_comments.comments, params.format);
+                let json = serde_json::to_string(&story_with_comments)?;
+
+                let mut response = Response::from_body(ResponseBody::Body(json.into_bytes()))?;
+                response
+                    .headers_mut()
+                    .set("Content-type", "application/json")?;
+
+                cors.apply_headers(response.headers_mut())?;
+
+                Ok(response)
+            }
+            Err(e) => Response::error(format!("Error fetching data: {}", e), 500),
+        }
+    } else if path.starts_with("/api/comment/") {
+        let comment_id = path.trim_start_matches("/api/comment/");
+        if !comment_id.chars().all(char::is_numeric) || comment_id.parse::<u32>().is_err() {
+            return Response::error("Invalid Comment ID", 400);
+        }
+
+        let params = match parse_common_params(&url) {
+            Ok(params) => params,
+            Err(e) => return Response::error(e.to_string(), 400),
+        };
+
+        match fetch_comment_thread(comment_id, params.max_depth, params.limit, params.sort_mode).await
+        {
+            Ok(mut thread) => {
+                render::render_tree(std::slice::from_mut(&mut thread.comment), params.format);
+                let json = serde_json::to_string(&thread)?;
+
+                let mut response = Response::from_body(ResponseBody::Body(json.into_bytes()))?;
+                response
+                    .headers_mut()
+                    .set("Content-type", "application/json")?;
+
+                cors.apply_headers(response.headers_mut())?;
+
+                Ok(response)
+            }
+            Err(e) => Response::error(format!("Error fetching data: {}", e), 500),
+        }
+    } else if let Some(list) = lists::StoryList::from_path(path) {
         let mut limit = None;
+        let mut cursor_token = None;
 
-        for (key, value) in query_params {
-            if key == "depth" {
-                if let Ok(depth) = value.parse::<u32>() {
-                    max_depth = Some(depth)
-                }
-            } else if key == "limit" {
+        for (key, value) in url.query_pairs() {
+            if key == "limit" {
                 if let Ok(lim) = value.parse::<usize>() {
                     limit = Some(lim)
                 }
+            } else if key == "cursor" {
+                cursor_token = Some(value.into_owned());
             }
         }
 
-        match fetch_story_with_comments(story_id, max_depth, limit).await {
-            Ok(story_with_comments) => {
-                let json = serde_json::to_string(&story_with_comments)?;
+        let filters = lists::ListFilters::parse(&url);
+
+        let start_index = match cursor_token {
+            Some(token) => match lists::ListCursor::decode(&token) {
+                Ok(parsed) if parsed.feed == list.feed_name() => parsed.start_index,
+                Ok(_) => return Response::error("Cursor does not match this list", 400),
+                Err(e) => return Response::error(format!("Invalid cursor: {}", e), 400),
+            },
+            None => 0,
+        };
+
+        match lists::fetch_page(list, start_index, limit, &filters).await {
+            Ok(page) => {
+                let json = serde_json::to_string(&page)?;
 
                 let mut response = Response::from_body(ResponseBody::Body(json.into_bytes()))?;
                 response
@@ -120,7 +266,15 @@ async fn main(req: Request, _env: Env, _ctx: Context) -> Result<Response> {
                 <h1>Hacker News Comments API</h1>
                 <p>Basic usage: <code>/api/story/{story_id}</code></p>
                 <p>Example: <a href="/api/story/36919310">/api/story/36919310</a></p>
-                
+
+                <p>Permalink to a comment and its surrounding context: <code>/api/comment/{comment_id}</code>, returning <code>{ ancestors, comment, story }</code></p>
+                <p>Accepts the same <code>depth</code>, <code>limit</code>, <code>sort</code>, and <code>format</code> parameters below, applied to the comment's own descendants.</p>
+
+                <h2>Story Lists</h2>
+                <p>Browse the Firebase story feeds directly: <code>/api/top</code>, <code>/api/new</code>, <code>/api/best</code>, <code>/api/ask</code>, <code>/api/show</code>, <code>/api/job</code>, each returning <code>{ stories, next_cursor }</code>.</p>
+                <p>Supported query params: <code>limit</code> (page size, default 30), <code>cursor</code> (from a previous <code>next_cursor</code>), <code>min_score</code>, <code>min_comments</code>, <code>author</code>, <code>before</code>/<code>after</code> (unix-time window).</p>
+                <p>Example: <a href="/api/top?limit=10&min_score=100">/api/top?limit=10&min_score=100</a></p>
+
                 <h2>Query Parameters</h2>
                 <table>
                     <tr>
@@ -131,7 +285,7 @@ async fn main(req: Request, _env: Env, _ctx: Context) -> Result<Response> {
                     </tr>
                     <tr>
                         <td>depth</td>
-                        <td>Comment recursion depth, 0 means top-level comments only</td>
+                        <td>Comment recursion depth, 0 means top-level comments only (capped at 50 regardless of request)</td>
                         <td>10</td>
                         <td><a href="/api/story/36919310?depth=2">?depth=2</a></td>
                     </tr>
@@ -141,6 +295,24 @@ async fn main(req: Request, _env: Env, _ctx: Context) -> Result<Response> {
                         <td>No limit</td>
                         <td><a href="/api/story/36919310?limit=5">?limit=5</a></td>
                     </tr>
+                    <tr>
+                        <td>after</td>
+                        <td>Opaque cursor (from a previous response's <code>next_cursor</code>) to fetch the next page of top-level comments</td>
+                        <td>First page</td>
+                        <td><a href="/api/story/36919310?limit=5">?limit=5</a> then <code>?limit=5&after={next_cursor}</code></td>
+                    </tr>
+                    <tr>
+                        <td>sort</td>
+                        <td>Comment order: <code>best</code> (HN's own order), <code>new</code>, <code>old</code>, or <code>top</code> (most replies first), applied recursively at every depth</td>
+                        <td>best</td>
+                        <td><a href="/api/story/36919310?sort=new">?sort=new</a></td>
+                    </tr>
+                    <tr>
+                        <td>format</td>
+                        <td>Comment text rendering: <code>html</code> (unchanged), <code>text</code> (plain text), or <code>markdown</code></td>
+                        <td>html</td>
+                        <td><a href="/api/story/36919310?format=markdown">?format=markdown</a></td>
+                    </tr>
                 </table>
                 
                 <h2>Combined Query Examples</h2>
@@ -186,46 +358,12 @@ async fn fetch_hn_item(item_id: &str) -> Result<HNItem> {
     Ok(item)
 }
 
-async fn fetch_comment_with_replies(
-    comment_id: u32,
-    max_depth: Option<u32>,
-) -> Result<CommentWithReplies> {
-    let comment = fetch_hn_item(&comment_id.to_string()).await?;
-
-    let mut replies = Vec::new();
-    let current_depth = max_depth.unwrap_or(10);
-
-    if current_depth > 0 {
-        if let Some(kids) = &comment.kids {
-            let next_depth = Some(current_depth - 1);
-            let fetches = kids
-                .iter()
-                .map(|&kid_id| fetch_comment_with_replies(kid_id, next_depth));
-
-            let results = join_all(fetches).await;
-
-            for result in results {
-                if let Ok(reply) = result {
-                    if reply.text.is_some() {
-                        replies.push(reply)
-                    }
-                }
-            }
-        }
-    }
-    Ok(CommentWithReplies {
-        id: comment_id,
-        by: comment.by,
-        time: comment.time,
-        text: comment.text,
-        replies,
-    })
-}
-
 async fn fetch_story_with_comments(
     story_id: &str,
     max_depth: Option<u32>,
     limit: Option<usize>,
+    start_index: usize,
+    sort_mode: SortMode,
 ) -> Result<StoryWithComments> {
     let story = fetch_hn_item(story_id).await?;
 
@@ -234,27 +372,253 @@ async fn fetch_story_with_comments(
     }
 
     let mut comments = Vec::new();
+    let mut next_cursor = None;
 
     if let Some(kids) = &story.kids {
-        let kid_ids = match limit {
-            Some(lim) => kids.iter().take(lim).cloned().collect::<Vec<u32>>(),
-            None => kids.clone(),
+        let end_index = match limit {
+            Some(lim) => kids.len().min(start_index.saturating_add(lim)),
+            None => kids.len(),
         };
+        let kid_ids = kids
+            .get(start_index..end_index)
+            .unwrap_or_default()
+            .to_vec();
 
-        let fetches = kid_ids
-            .iter()
-            .map(|&kid_id| fetch_comment_with_replies(kid_id, max_depth));
+        if end_index < kids.len() {
+            next_cursor = Some(Cursor::encode(story.id, end_index));
+        }
 
-        let results = join_all(fetches).await;
+        let items = comments::fetch_tree(&kid_ids, max_depth.unwrap_or(10)).await;
 
-        for result in results {
-            if let Ok(comment) = result {
-                if comment.text.is_some() {
-                    comments.push(comment);
-                }
+        for &kid_id in &kid_ids {
+            if let Some(comment) = comments::assemble(kid_id, &items) {
+                comments.push(comment);
             }
         }
+
+        sort::sort_comments(&mut comments, sort_mode);
+    }
+
+    Ok(StoryWithComments {
+        story,
+        comments,
+        next_cursor,
+    })
+}
+
+async fn fetch_comment_thread(
+    comment_id: &str,
+    max_depth: Option<u32>,
+    limit: Option<usize>,
+    sort_mode: SortMode,
+) -> Result<CommentThread> {
+    let root_id: u32 = comment_id
+        .parse()
+        .map_err(|_| Error::from("Invalid Comment ID"))?;
+
+    let root = fetch_hn_item(comment_id).await?;
+    if root.item_type != "comment" {
+        return Err(Error::from("Provided ID is not a comment"));
     }
 
-    Ok(StoryWithComments { story, comments })
+    let kid_ids = match (&root.kids, limit) {
+        (Some(kids), Some(lim)) => kids.iter().take(lim).cloned().collect::<Vec<u32>>(),
+        (Some(kids), None) => kids.clone(),
+        (None, _) => Vec::new(),
+    };
+
+    let parent_id = root.parent;
+
+    let mut items = comments::fetch_tree(&kid_ids, max_depth.unwrap_or(10)).await;
+    items.insert(root_id, root);
+
+    let mut comment = comments::assemble(root_id, &items)
+        .ok_or_else(|| Error::from("Comment is deleted or has no text"))?;
+    sort::sort_comments(&mut comment.replies, sort_mode);
+
+    let (ancestors, story) = fetch_ancestors(parent_id).await;
+
+    Ok(CommentThread {
+        ancestors,
+        comment,
+        story,
+    })
+}
+
+/**
+ * Walks a comment's `parent` chain iteratively (not recursively) until it
+ * reaches the story the thread belongs to, returning the ancestor chain
+ * (immediate parent first) alongside that story.
+ *
+ * A deleted/dead ancestor still resolves as a normal item and the walk
+ * continues through it. If an ancestor fetch itself fails (e.g. Firebase
+ * returns a malformed or missing item), the walk stops there and `story`
+ * comes back `None` rather than failing the whole request — the comment
+ * and its replies are still perfectly valid on their own.
+ */
+async fn fetch_ancestors(parent_id: Option<u32>) -> (Vec<HNItem>, Option<HNItem>) {
+    walk_ancestors(parent_id, |id| async move { fetch_hn_item(&id.to_string()).await }).await
+}
+
+/// The control flow behind `fetch_ancestors`, parameterized over the fetch
+/// itself so the walk's termination and failure handling can be exercised
+/// with a fake parent chain in tests, without a network round trip.
+async fn walk_ancestors<F, Fut>(mut parent_id: Option<u32>, mut fetch: F) -> (Vec<HNItem>, Option<HNItem>)
+where
+    F: FnMut(u32) -> Fut,
+    Fut: std::future::Future<Output = Result<HNItem>>,
+{
+    let mut ancestors = Vec::new();
+
+    loop {
+        let id = match parent_id {
+            Some(id) => id,
+            None => return (ancestors, None),
+        };
+
+        let item = match fetch(id).await {
+            Ok(item) => item,
+            Err(_) => return (ancestors, None),
+        };
+
+        if item.item_type == "story" {
+            return (ancestors, Some(item));
+        }
+
+        parent_id = item.parent;
+        ancestors.push(item);
+
+        if ancestors.len() as u32 > comments::MAX_DEPTH {
+            return (ancestors, None);
+        }
+    }
+}
+
+#[cfg(test)]
+mod ancestor_tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    fn comment_item(id: u32, parent: Option<u32>) -> HNItem {
+        HNItem {
+            id,
+            item_type: "comment".to_string(),
+            deleted: None,
+            by: Some("someone".to_string()),
+            time: Some(1000),
+            text: Some("a comment".to_string()),
+            dead: None,
+            parent,
+            poll: None,
+            kids: None,
+            url: None,
+            score: None,
+            title: None,
+            parts: None,
+            descendants: None,
+        }
+    }
+
+    fn story_item(id: u32) -> HNItem {
+        HNItem {
+            id,
+            item_type: "story".to_string(),
+            deleted: None,
+            by: Some("someone".to_string()),
+            time: Some(1000),
+            text: None,
+            dead: None,
+            parent: None,
+            poll: None,
+            kids: None,
+            url: None,
+            score: None,
+            title: Some("a story".to_string()),
+            parts: None,
+            descendants: None,
+        }
+    }
+
+    fn fetch_from(chain: Arc<HashMap<u32, HNItem>>) -> impl FnMut(u32) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<HNItem>>>> {
+        move |id| {
+            let chain = chain.clone();
+            Box::pin(async move {
+                chain
+                    .get(&id)
+                    .cloned()
+                    .ok_or_else(|| Error::from("missing item"))
+            })
+        }
+    }
+
+    async fn never_called(_id: u32) -> Result<HNItem> {
+        unreachable!("walk_ancestors must not fetch when there is no parent to follow")
+    }
+
+    #[test]
+    fn stops_at_the_story_and_returns_the_chain_in_order() {
+        let mut chain = HashMap::new();
+        chain.insert(2, comment_item(2, Some(3)));
+        chain.insert(3, story_item(3));
+
+        let (ancestors, story) =
+            futures::executor::block_on(walk_ancestors(Some(2), fetch_from(Arc::new(chain))));
+
+        assert_eq!(ancestors.len(), 1);
+        assert_eq!(ancestors[0].id, 2);
+        assert_eq!(story.map(|s| s.id), Some(3));
+    }
+
+    #[test]
+    fn gives_up_gracefully_when_an_ancestor_fetch_fails() {
+        // id 5 is never in the map, simulating a failed/missing fetch.
+        let chain = HashMap::new();
+
+        let (ancestors, story) =
+            futures::executor::block_on(walk_ancestors(Some(5), fetch_from(Arc::new(chain))));
+
+        assert!(ancestors.is_empty());
+        assert!(story.is_none());
+    }
+
+    #[test]
+    fn gives_up_partway_and_keeps_the_ancestors_seen_so_far() {
+        let mut chain = HashMap::new();
+        chain.insert(2, comment_item(2, Some(3)));
+        // id 3's fetch fails, so the story is never reached.
+        chain.insert(4, comment_item(4, Some(2)));
+
+        let (ancestors, story) =
+            futures::executor::block_on(walk_ancestors(Some(4), fetch_from(Arc::new(chain))));
+
+        assert_eq!(ancestors.iter().map(|a| a.id).collect::<Vec<_>>(), vec![4, 2]);
+        assert!(story.is_none());
+    }
+
+    #[test]
+    fn returns_none_immediately_when_there_is_no_parent() {
+        let (ancestors, story) = futures::executor::block_on(walk_ancestors(None, never_called));
+
+        assert!(ancestors.is_empty());
+        assert!(story.is_none());
+    }
+
+    #[test]
+    fn terminates_on_a_cyclic_chain_that_never_reaches_a_story() {
+        // A pure cycle with no story and no missing id anywhere: nothing but
+        // the MAX_DEPTH guard can stop this walk from looping forever.
+        let cycle_len = comments::MAX_DEPTH + 5;
+        let mut chain = HashMap::new();
+        for id in 1..=cycle_len {
+            let parent = if id == cycle_len { 1 } else { id + 1 };
+            chain.insert(id, comment_item(id, Some(parent)));
+        }
+
+        let (ancestors, story) =
+            futures::executor::block_on(walk_ancestors(Some(1), fetch_from(Arc::new(chain))));
+
+        assert!(story.is_none());
+        assert_eq!(ancestors.len() as u32, comments::MAX_DEPTH + 1);
+    }
 }
@@ -0,0 +1,370 @@
+use crate::cursor;
+use futures::future::join_all;
+use serde::Serialize;
+use worker::{Error, Fetch, Method, Request, Result, Url};
+
+/// Default page size when a request doesn't specify `limit`.
+const DEFAULT_LIMIT: usize = 30;
+
+/**
+ * One of the Firebase story-list feeds, keyed by the path that selects it.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StoryList {
+    Top,
+    New,
+    Best,
+    Ask,
+    Show,
+    Job,
+}
+
+impl StoryList {
+    pub fn from_path(path: &str) -> Option<StoryList> {
+        match path {
+            "/api/top" => Some(StoryList::Top),
+            "/api/new" => Some(StoryList::New),
+            "/api/best" => Some(StoryList::Best),
+            "/api/ask" => Some(StoryList::Ask),
+            "/api/show" => Some(StoryList::Show),
+            "/api/job" => Some(StoryList::Job),
+            _ => None,
+        }
+    }
+
+    pub fn feed_name(self) -> &'static str {
+        match self {
+            StoryList::Top => "topstories",
+            StoryList::New => "newstories",
+            StoryList::Best => "beststories",
+            StoryList::Ask => "askstories",
+            StoryList::Show => "showstories",
+            StoryList::Job => "jobstories",
+        }
+    }
+}
+
+/// Opaque pagination cursor for a story-list feed: the feed name plus the
+/// offset into its id array the next page should resume at. Reuses the same
+/// base64 encoding as the per-story comment cursor.
+pub struct ListCursor {
+    pub feed: String,
+    pub start_index: usize,
+}
+
+impl ListCursor {
+    pub fn encode(list: StoryList, start_index: usize) -> String {
+        cursor::encode_base64(format!("{}:{}", list.feed_name(), start_index).as_bytes())
+    }
+
+    pub fn decode(token: &str) -> Result<ListCursor> {
+        let bytes =
+            cursor::decode_base64(token).ok_or_else(|| Error::from("Invalid cursor encoding"))?;
+        let raw = String::from_utf8(bytes).map_err(|_| Error::from("Invalid cursor encoding"))?;
+
+        let (feed, start_index) = raw
+            .split_once(':')
+            .ok_or_else(|| Error::from("Invalid cursor contents"))?;
+
+        Ok(ListCursor {
+            feed: feed.to_string(),
+            start_index: start_index
+                .parse()
+                .map_err(|_| Error::from("Invalid cursor contents"))?,
+        })
+    }
+}
+
+/// Numeric/textual filters applied to each fetched page of stories.
+#[derive(Default)]
+pub struct ListFilters {
+    pub min_score: Option<u32>,
+    pub min_descendants: Option<u32>,
+    pub author: Option<String>,
+    pub before: Option<u64>,
+    pub after: Option<u64>,
+}
+
+impl ListFilters {
+    pub fn parse(url: &Url) -> ListFilters {
+        let mut filters = ListFilters::default();
+
+        for (key, value) in url.query_pairs() {
+            match key.as_ref() {
+                "min_score" => filters.min_score = value.parse().ok(),
+                "min_comments" => filters.min_descendants = value.parse().ok(),
+                "author" => filters.author = Some(value.into_owned()),
+                "before" => filters.before = value.parse().ok(),
+                "after" => filters.after = value.parse().ok(),
+                _ => {}
+            }
+        }
+
+        filters
+    }
+
+    fn matches(&self, item: &crate::HNItem) -> bool {
+        if let Some(min_score) = self.min_score {
+            if item.score.unwrap_or(0) < min_score {
+                return false;
+            }
+        }
+
+        if let Some(min_descendants) = self.min_descendants {
+            if item.descendants.unwrap_or(0) < min_descendants {
+                return false;
+            }
+        }
+
+        if let Some(author) = &self.author {
+            if item.by.as_deref() != Some(author.as_str()) {
+                return false;
+            }
+        }
+
+        if let Some(before) = self.before {
+            if item.time.unwrap_or(0) > before {
+                return false;
+            }
+        }
+
+        if let Some(after) = self.after {
+            if item.time.unwrap_or(0) < after {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+#[derive(Serialize)]
+pub struct StoryListPage {
+    pub stories: Vec<crate::HNItem>,
+    pub next_cursor: Option<String>,
+}
+
+async fn fetch_id_list(list: StoryList) -> Result<Vec<u32>> {
+    let url = format!(
+        "https://hacker-news.firebaseio.com/v0/{}.json",
+        list.feed_name()
+    );
+    let mut req = Request::new(&url, Method::Get)?;
+
+    req.headers_mut()?.set("Accept", "application/json")?;
+
+    let mut resp = Fetch::Request(req).send().await?;
+
+    let status = resp.status_code();
+    if !(200..=299).contains(&status) {
+        return Err(Error::from(format!(
+            "Failed to fetch {} list: HTTP {}",
+            list.feed_name(),
+            status
+        )));
+    }
+
+    let body = resp.text().await?;
+    let ids: Vec<u32> = serde_json::from_str(&body)?;
+
+    Ok(ids)
+}
+
+/**
+ * Fetches one page of a story-list feed and applies `filters`. Only the
+ * ids in this page are resolved to full items, concurrently via
+ * `join_all`, rather than the whole (potentially hundreds-long) feed.
+ */
+pub async fn fetch_page(
+    list: StoryList,
+    start_index: usize,
+    limit: Option<usize>,
+    filters: &ListFilters,
+) -> Result<StoryListPage> {
+    let ids = fetch_id_list(list).await?;
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT);
+    let end_index = page_end_index(ids.len(), start_index, limit);
+    let page_ids = ids.get(start_index..end_index).unwrap_or_default().to_vec();
+
+    let next_cursor = if end_index < ids.len() {
+        Some(ListCursor::encode(list, end_index))
+    } else {
+        None
+    };
+
+    let fetches = page_ids.iter().map(|&id| crate::fetch_hn_item(&id.to_string()));
+    let results = join_all(fetches).await;
+
+    let mut stories = Vec::new();
+    for result in results {
+        if let Ok(item) = result {
+            if filters.matches(&item) {
+                stories.push(item);
+            }
+        }
+    }
+
+    Ok(StoryListPage {
+        stories,
+        next_cursor,
+    })
+}
+
+/// Computes the exclusive end index of a page given the total id count,
+/// clamping to `total` so a `start_index`/`limit` past the end of the feed
+/// yields an empty page instead of a panic.
+fn page_end_index(total: usize, start_index: usize, limit: usize) -> usize {
+    total.min(start_index.saturating_add(limit))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(id: u32) -> crate::HNItem {
+        crate::HNItem {
+            id,
+            item_type: "story".to_string(),
+            deleted: None,
+            by: Some("someone".to_string()),
+            time: Some(1_000),
+            text: None,
+            dead: None,
+            parent: None,
+            poll: None,
+            kids: None,
+            url: None,
+            score: Some(50),
+            title: Some("a story".to_string()),
+            parts: None,
+            descendants: Some(10),
+        }
+    }
+
+    #[test]
+    fn min_score_rejects_and_accepts() {
+        let filters = ListFilters {
+            min_score: Some(51),
+            ..Default::default()
+        };
+        assert!(!filters.matches(&item(1)));
+
+        let filters = ListFilters {
+            min_score: Some(50),
+            ..Default::default()
+        };
+        assert!(filters.matches(&item(1)));
+    }
+
+    #[test]
+    fn min_comments_rejects_and_accepts() {
+        let filters = ListFilters {
+            min_descendants: Some(11),
+            ..Default::default()
+        };
+        assert!(!filters.matches(&item(1)));
+
+        let filters = ListFilters {
+            min_descendants: Some(10),
+            ..Default::default()
+        };
+        assert!(filters.matches(&item(1)));
+    }
+
+    #[test]
+    fn author_rejects_and_accepts() {
+        let filters = ListFilters {
+            author: Some("someone-else".to_string()),
+            ..Default::default()
+        };
+        assert!(!filters.matches(&item(1)));
+
+        let filters = ListFilters {
+            author: Some("someone".to_string()),
+            ..Default::default()
+        };
+        assert!(filters.matches(&item(1)));
+    }
+
+    #[test]
+    fn before_rejects_and_accepts() {
+        let filters = ListFilters {
+            before: Some(999),
+            ..Default::default()
+        };
+        assert!(!filters.matches(&item(1)));
+
+        let filters = ListFilters {
+            before: Some(1_000),
+            ..Default::default()
+        };
+        assert!(filters.matches(&item(1)));
+    }
+
+    #[test]
+    fn after_rejects_and_accepts() {
+        let filters = ListFilters {
+            after: Some(1_001),
+            ..Default::default()
+        };
+        assert!(!filters.matches(&item(1)));
+
+        let filters = ListFilters {
+            after: Some(1_000),
+            ..Default::default()
+        };
+        assert!(filters.matches(&item(1)));
+    }
+
+    #[test]
+    fn cursor_round_trips() {
+        for (list, start_index) in [
+            (StoryList::Top, 0usize),
+            (StoryList::Show, 42),
+            (StoryList::Job, 12345),
+        ] {
+            let token = ListCursor::encode(list, start_index);
+            let decoded = ListCursor::decode(&token).expect("encoded cursor should decode");
+            assert_eq!(decoded.feed, list.feed_name());
+            assert_eq!(decoded.start_index, start_index);
+        }
+    }
+
+    #[test]
+    fn page_end_index_clamps_to_the_total_id_count() {
+        assert_eq!(page_end_index(5, 3, 10), 5);
+        assert_eq!(page_end_index(5, 0, 2), 2);
+        assert_eq!(page_end_index(5, 5, 10), 5);
+    }
+
+    #[test]
+    fn next_cursor_is_some_even_when_filtering_drops_every_item_in_the_page() {
+        // end_index < total drives next_cursor, independent of filters, so a
+        // page that's entirely filtered out still reports a next page.
+        let end_index = page_end_index(5, 0, 2);
+        let next_cursor = (end_index < 5).then(|| ListCursor::encode(StoryList::Top, end_index));
+
+        assert!(next_cursor.is_some());
+
+        let filters = ListFilters {
+            min_score: Some(u32::MAX),
+            ..Default::default()
+        };
+        let stories: Vec<crate::HNItem> = vec![item(1), item(2)]
+            .into_iter()
+            .filter(|i| filters.matches(i))
+            .collect();
+
+        assert!(stories.is_empty());
+        assert!(next_cursor.is_some());
+    }
+
+    #[test]
+    fn next_cursor_is_none_on_the_last_page() {
+        let end_index = page_end_index(5, 0, 10);
+        let next_cursor = (end_index < 5).then(|| ListCursor::encode(StoryList::Top, end_index));
+
+        assert!(next_cursor.is_none());
+    }
+}
@@ -0,0 +1,255 @@
+/**
+ * Renders HN's HTML comment fragments into plain text or Markdown.
+ *
+ * HN comment `text` fields are small, mostly well-formed HTML fragments
+ * (`<p>`, `<a href>`, `<i>`, `<pre><code>`, and entities), but not
+ * guaranteed to be, so this walks the markup as a token stream instead of
+ * using regex, which handles nested and malformed tags safely.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderFormat {
+    Html,
+    Text,
+    Markdown,
+}
+
+impl RenderFormat {
+    pub fn parse(value: &str) -> Option<RenderFormat> {
+        match value {
+            "html" => Some(RenderFormat::Html),
+            "text" => Some(RenderFormat::Text),
+            "markdown" => Some(RenderFormat::Markdown),
+            _ => None,
+        }
+    }
+}
+
+enum Token<'a> {
+    OpenTag(&'a str),
+    CloseTag(&'a str),
+    Text(&'a str),
+}
+
+/// Splits an HTML fragment into a flat stream of open tags, close tags, and
+/// text runs. Open tags keep their raw contents, attributes included;
+/// `render` reads only `href` on `<a>` and ignores every other attribute.
+fn tokenize(html: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let bytes = html.as_bytes();
+    let mut i = 0;
+    let mut text_start = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'<' {
+            if text_start < i {
+                tokens.push(Token::Text(&html[text_start..i]));
+            }
+
+            if let Some(end) = html[i..].find('>') {
+                let tag = &html[i + 1..i + end];
+                if let Some(name) = tag.strip_prefix('/') {
+                    tokens.push(Token::CloseTag(name.trim()));
+                } else {
+                    tokens.push(Token::OpenTag(tag.trim()));
+                }
+                i += end + 1;
+            } else {
+                // Unterminated tag: treat the rest as text.
+                tokens.push(Token::Text(&html[i..]));
+                i = bytes.len();
+            }
+            text_start = i;
+        } else {
+            i += 1;
+        }
+    }
+
+    if text_start < bytes.len() {
+        tokens.push(Token::Text(&html[text_start..]));
+    }
+
+    tokens
+}
+
+fn tag_name(tag: &str) -> &str {
+    tag.split_whitespace().next().unwrap_or(tag)
+}
+
+fn href_attr(tag: &str) -> Option<String> {
+    let lower = tag.to_ascii_lowercase();
+    let idx = lower.find("href")?;
+    let rest = &tag[idx + 4..];
+    let rest = rest.trim_start();
+    let rest = rest.strip_prefix('=')?.trim_start();
+    let quote = rest.chars().next()?;
+
+    if quote == '"' || quote == '\'' {
+        let end = rest[1..].find(quote)? + 1;
+        Some(decode_entities(&rest[1..end]))
+    } else {
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        Some(decode_entities(&rest[..end]))
+    }
+}
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&quot;", "\"")
+        .replace("&#x27;", "'")
+        .replace("&#39;", "'")
+        .replace("&apos;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+/// Renders `text` on every comment in the tree into the requested format.
+pub fn render_tree(comments: &mut [crate::CommentWithReplies], format: RenderFormat) {
+    if format == RenderFormat::Html {
+        return;
+    }
+
+    for comment in comments.iter_mut() {
+        if let Some(text) = &comment.text {
+            comment.text = Some(render(text, format));
+        }
+        render_tree(&mut comment.replies, format);
+    }
+}
+
+/// Renders an HN comment HTML fragment into the requested format.
+pub fn render(html: &str, format: RenderFormat) -> String {
+    if format == RenderFormat::Html {
+        return html.to_string();
+    }
+
+    let tokens = tokenize(html);
+    let mut out = String::with_capacity(html.len());
+    let mut pending_href: Vec<Option<String>> = Vec::new();
+    let mut link_text_start: Vec<usize> = Vec::new();
+    let mut in_pre = false;
+
+    for token in tokens {
+        match token {
+            Token::Text(text) => {
+                out.push_str(&decode_entities(text));
+            }
+            Token::OpenTag(tag) => match tag_name(tag) {
+                "p" => {
+                    if !out.is_empty() {
+                        out.push_str("\n\n");
+                    }
+                }
+                "a" => {
+                    pending_href.push(href_attr(tag));
+                    link_text_start.push(out.len());
+                }
+                "i" | "em" => {
+                    if format == RenderFormat::Markdown {
+                        out.push('*');
+                    }
+                }
+                "pre" => in_pre = true,
+                "code" => {
+                    if format == RenderFormat::Markdown {
+                        out.push_str(if in_pre { "```\n" } else { "`" });
+                    }
+                }
+                _ => {}
+            },
+            Token::CloseTag(name) => match name {
+                "p" => {}
+                "a" => {
+                    if let Some(href) = pending_href.pop() {
+                        let start = link_text_start.pop().unwrap_or(out.len());
+                        let link_text = out[start..].to_string();
+                        out.truncate(start);
+
+                        match (format, href) {
+                            (RenderFormat::Markdown, Some(href)) => {
+                                out.push_str(&format!("[{}]({})", link_text, href));
+                            }
+                            (RenderFormat::Text, Some(href)) => {
+                                out.push_str(&format!("{} ({})", link_text, href));
+                            }
+                            (RenderFormat::Html, _) | (_, None) => out.push_str(&link_text),
+                        }
+                    }
+                }
+                "i" | "em" => {
+                    if format == RenderFormat::Markdown {
+                        out.push('*');
+                    }
+                }
+                "pre" => in_pre = false,
+                "code" => {
+                    if format == RenderFormat::Markdown {
+                        out.push_str(if in_pre { "\n```" } else { "`" });
+                    }
+                }
+                _ => {}
+            },
+        }
+    }
+
+    out.trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn html_format_is_unchanged() {
+        let html = "<p>Hello <i>world</i></p>";
+        assert_eq!(render(html, RenderFormat::Html), html);
+    }
+
+    #[test]
+    fn paragraphs_become_blank_line_separators() {
+        let html = "<p>First</p><p>Second</p>";
+        assert_eq!(render(html, RenderFormat::Text), "First\n\nSecond");
+        assert_eq!(render(html, RenderFormat::Markdown), "First\n\nSecond");
+    }
+
+    #[test]
+    fn links_render_per_format() {
+        let html = r#"<a href="https://example.com">example</a>"#;
+        assert_eq!(
+            render(html, RenderFormat::Text),
+            "example (https://example.com)"
+        );
+        assert_eq!(
+            render(html, RenderFormat::Markdown),
+            "[example](https://example.com)"
+        );
+    }
+
+    #[test]
+    fn pre_code_blocks_are_fenced_only_in_markdown() {
+        let html = "<pre><code>fn main() {}</code></pre>";
+        assert_eq!(render(html, RenderFormat::Text), "fn main() {}");
+        assert_eq!(
+            render(html, RenderFormat::Markdown),
+            "```\nfn main() {}\n```"
+        );
+    }
+
+    #[test]
+    fn italics_become_asterisks_only_in_markdown() {
+        let html = "<i>emphasis</i>";
+        assert_eq!(render(html, RenderFormat::Text), "emphasis");
+        assert_eq!(render(html, RenderFormat::Markdown), "*emphasis*");
+    }
+
+    #[test]
+    fn entities_are_decoded() {
+        let html = "Tom &amp; Jerry &lt;3";
+        assert_eq!(render(html, RenderFormat::Text), "Tom & Jerry <3");
+    }
+
+    #[test]
+    fn malformed_unterminated_tag_is_kept_as_text() {
+        let html = "hello <b unterminated";
+        assert_eq!(render(html, RenderFormat::Text), "hello <b unterminated");
+    }
+}
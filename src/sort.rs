@@ -0,0 +1,110 @@
+/**
+ * Ordering applied to a level of comments before it is returned.
+ *
+ * `Best` is the default and simply preserves the order HN's `kids` array
+ * already arrives in (the site's own ranking). The others re-sort that
+ * level; sorting is applied recursively so every depth of a thread
+ * honors the same order.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortMode {
+    Best,
+    New,
+    Old,
+    Top,
+}
+
+impl SortMode {
+    pub fn parse(value: &str) -> Option<SortMode> {
+        match value {
+            "best" => Some(SortMode::Best),
+            "new" => Some(SortMode::New),
+            "old" => Some(SortMode::Old),
+            "top" => Some(SortMode::Top),
+            _ => None,
+        }
+    }
+}
+
+/**
+ * Sorts a level of comments in place according to `mode`, recursing into
+ * `replies` first so every nested level ends up in the same order.
+ *
+ * "Top" is approximated by reply count, since the number of descendants
+ * on HN's own item isn't carried through once only the fetched replies
+ * with text are kept.
+ */
+pub fn sort_comments(comments: &mut [crate::CommentWithReplies], mode: SortMode) {
+    for comment in comments.iter_mut() {
+        sort_comments(&mut comment.replies, mode);
+    }
+
+    match mode {
+        SortMode::Best => {}
+        SortMode::New => comments.sort_by(|a, b| b.time.cmp(&a.time)),
+        SortMode::Old => comments.sort_by(|a, b| a.time.cmp(&b.time)),
+        SortMode::Top => comments.sort_by(|a, b| b.replies.len().cmp(&a.replies.len())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn comment(id: u32, time: u64, replies: Vec<crate::CommentWithReplies>) -> crate::CommentWithReplies {
+        crate::CommentWithReplies {
+            id,
+            by: Some("someone".to_string()),
+            time: Some(time),
+            text: Some("a comment".to_string()),
+            replies,
+        }
+    }
+
+    fn ids(comments: &[crate::CommentWithReplies]) -> Vec<u32> {
+        comments.iter().map(|c| c.id).collect()
+    }
+
+    #[test]
+    fn best_leaves_the_original_order_untouched() {
+        let mut comments = vec![comment(1, 100, vec![]), comment(2, 200, vec![])];
+        sort_comments(&mut comments, SortMode::Best);
+        assert_eq!(ids(&comments), vec![1, 2]);
+    }
+
+    #[test]
+    fn new_orders_most_recent_first() {
+        let mut comments = vec![comment(1, 100, vec![]), comment(2, 300, vec![]), comment(3, 200, vec![])];
+        sort_comments(&mut comments, SortMode::New);
+        assert_eq!(ids(&comments), vec![2, 3, 1]);
+    }
+
+    #[test]
+    fn old_orders_oldest_first() {
+        let mut comments = vec![comment(1, 100, vec![]), comment(2, 300, vec![]), comment(3, 200, vec![])];
+        sort_comments(&mut comments, SortMode::Old);
+        assert_eq!(ids(&comments), vec![1, 3, 2]);
+    }
+
+    #[test]
+    fn top_orders_by_reply_count_descending() {
+        let mut comments = vec![
+            comment(1, 100, vec![comment(10, 100, vec![])]),
+            comment(2, 100, vec![]),
+            comment(3, 100, vec![comment(30, 100, vec![]), comment(31, 100, vec![])]),
+        ];
+        sort_comments(&mut comments, SortMode::Top);
+        assert_eq!(ids(&comments), vec![3, 1, 2]);
+    }
+
+    #[test]
+    fn ordering_is_applied_recursively_at_every_depth() {
+        let mut comments = vec![comment(
+            1,
+            100,
+            vec![comment(3, 200, vec![]), comment(2, 300, vec![])],
+        )];
+        sort_comments(&mut comments, SortMode::New);
+        assert_eq!(ids(&comments[0].replies), vec![2, 3]);
+    }
+}